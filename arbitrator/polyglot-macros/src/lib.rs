@@ -0,0 +1,306 @@
+// Copyright 2022, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+//! `#[host_interface]` turns a trait of host methods into the wasmer
+//! `Imports` that `WasmEnv` needs, so adding a Stylus hostio is a matter
+//! of declaring a trait method instead of hand-wiring a call site. It
+//! also emits a typed client shim so tests can invoke a hostio directly
+//! — without wiring a guest `Instance` at all — by calling straight into
+//! the same dispatch logic the registered import uses.
+//!
+//! Arguments follow the same pass-by-codec vs pass-by-inner split as
+//! runtime-interface designs: scalars (`i32`/`i64`/`f64`/`u32`/`u64`)
+//! cross the host/guest boundary directly as wasm params, while anything
+//! larger is encoded into a guest-provided buffer and only the `(ptr,
+//! len)` pair crosses as a pair of `u32`s — the shim takes the same raw
+//! `(ptr, len)` params as the registered import, so it's the caller's
+//! job to have already written the encoded bytes into `env`'s memory
+//! (exactly what a real guest would do before making the call).
+//!
+//! Every generated import charges a flat dispatch cost (via the trait's
+//! `InkCost` supertrait) before doing any work; methods that want to
+//! charge more, e.g. proportional to an input length, can buy additional
+//! ink themselves once they have `env` in scope (see the `env: &mut
+//! WasmEnv` leading-parameter note below).
+//!
+//! Host methods return `()` or `eyre::Result<()>`: any value a guest
+//! needs goes back through an output pointer the method writes to
+//! itself (the same pattern the `env: &mut WasmEnv` parameter uses for
+//! input), not through the trait's return type. A method that can fail
+//! on attacker-reachable input (a bad pointer, say) should return
+//! `eyre::Result<()>` and propagate with `?`, rather than panicking —
+//! any other return type is rejected at expansion time rather than
+//! silently dropped.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, FnArg, GenericArgument, ItemTrait, Pat, PathArguments, ReturnType, TraitItem,
+    Type,
+};
+
+/// Whether a type crosses the host/guest boundary as a bare scalar, or
+/// must be encoded into a guest buffer and passed as `(ptr, len)`.
+enum Abi {
+    Scalar(TokenStream2),
+    Encoded,
+}
+
+fn abi_of(ty: &Type) -> Abi {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            return match segment.ident.to_string().as_str() {
+                "i32" | "u32" => Abi::Scalar(quote!(i32)),
+                "i64" | "u64" => Abi::Scalar(quote!(i64)),
+                "f32" => Abi::Scalar(quote!(f32)),
+                "f64" => Abi::Scalar(quote!(f64)),
+                _ => Abi::Encoded,
+            };
+        }
+    }
+    Abi::Encoded
+}
+
+/// Whether a method's declared return type is infallible (`()`) or
+/// fallible (`eyre::Result<()>`); for anything else `classify_return`
+/// gives `None`, which the caller rejects.
+enum Return {
+    Unit,
+    Fallible,
+}
+
+fn classify_return(output: &ReturnType) -> Option<Return> {
+    match output {
+        ReturnType::Default => Some(Return::Unit),
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(unit) if unit.elems.is_empty() => Some(Return::Unit),
+            Type::Path(path) => {
+                let last = path.path.segments.last()?;
+                let PathArguments::AngleBracketed(generics) = &last.arguments else {
+                    return None;
+                };
+                let ok_is_unit = matches!(
+                    generics.args.first(),
+                    Some(GenericArgument::Type(Type::Tuple(unit))) if unit.elems.is_empty()
+                );
+                (last.ident == "Result" && ok_is_unit).then_some(Return::Fallible)
+            }
+            _ => None,
+        },
+    }
+}
+
+struct Arg {
+    /// Parameter(s) the registered import and the shim both take.
+    param: TokenStream2,
+    /// The bare identifier(s) from `param`, for forwarding into a call.
+    forward: TokenStream2,
+    /// Decodes `param` into the value the trait method actually expects.
+    marshal: TokenStream2,
+    /// The decoded value, passed on to the trait method call.
+    call_arg: TokenStream2,
+}
+
+fn lower_arg(index: usize, ty: &Type) -> Arg {
+    match abi_of(ty) {
+        Abi::Scalar(wasm_ty) => {
+            let name = format_ident!("arg{index}");
+            Arg {
+                param: quote!(#name: #wasm_ty),
+                forward: quote!(#name),
+                marshal: quote!(let #name: #ty = #name as #ty;),
+                call_arg: quote!(#name),
+            }
+        }
+        Abi::Encoded => {
+            let ptr = format_ident!("arg{index}_ptr");
+            let len = format_ident!("arg{index}_len");
+            let name = format_ident!("arg{index}");
+            Arg {
+                param: quote!(#ptr: u32, #len: u32),
+                forward: quote!(#ptr, #len),
+                marshal: quote! {
+                    let bytes = env.read_slice(#ptr, #len)?;
+                    let #name: #ty = ::arbutil::codec::decode(&bytes)?;
+                },
+                call_arg: quote!(#name),
+            }
+        }
+    }
+}
+
+/// Generates the `Imports` registration and a typed client shim for a
+/// trait of host methods, e.g.:
+///
+/// ```ignore
+/// #[host_interface]
+/// trait Crypto: InkCost {
+///     fn keccak256(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> eyre::Result<()>;
+/// }
+/// ```
+///
+/// Methods must return `()` or `eyre::Result<()>`; this is enforced at
+/// expansion time.
+#[proc_macro_attribute]
+pub fn host_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let trait_item = parse_macro_input!(item as ItemTrait);
+    let trait_name = &trait_item.ident;
+    let snake_name = to_snake(&trait_item.ident.to_string());
+    let imports_fn = format_ident!("{snake_name}_imports");
+    let shim_name = format_ident!("{}Shim", trait_item.ident);
+
+    let mut registrations = Vec::new();
+    let mut dispatch_fns = Vec::new();
+    let mut shim_methods = Vec::new();
+
+    for member in &trait_item.items {
+        let TraitItem::Fn(method) = member else {
+            continue;
+        };
+        let name = &method.sig.ident;
+        let import_name = name.to_string();
+        let dispatch_fn = format_ident!("{snake_name}_{name}_dispatch");
+
+        let Some(ret) = classify_return(&method.sig.output) else {
+            return syn::Error::new_spanned(
+                &method.sig.output,
+                "#[host_interface] methods must return `()` or `eyre::Result<()>`; \
+                 marshal results back through an output pointer instead",
+            )
+            .to_compile_error()
+            .into();
+        };
+
+        // A leading `env: &mut WasmEnv` parameter opts a method into raw
+        // guest-memory access (for hostios that read/write buffers at
+        // guest-supplied pointers); the parameters after it are still
+        // marshaled per argument.
+        let mut inputs = method.sig.inputs.iter().skip(1); // &self
+        let wants_env = matches!(
+            inputs.clone().next(),
+            Some(FnArg::Typed(pat_type))
+                if matches!(&*pat_type.pat, Pat::Ident(ident) if ident.ident == "env")
+        );
+        if wants_env {
+            inputs.next();
+        }
+
+        let args: Vec<Arg> = inputs
+            .enumerate()
+            .filter_map(|(index, input)| match input {
+                FnArg::Typed(pat_type) => {
+                    if matches!(&*pat_type.pat, Pat::Ident(_)) {
+                        Some(lower_arg(index, &pat_type.ty))
+                    } else {
+                        None
+                    }
+                }
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+
+        let params = args.iter().map(|a| &a.param);
+        let marshal = args.iter().map(|a| &a.marshal);
+        let call_args = args.iter().map(|a| &a.call_arg);
+
+        let call = if wants_env {
+            quote! { host.#name(env, #(#call_args),*) }
+        } else {
+            quote! { host.#name(#(#call_args),*) }
+        };
+        let call = match ret {
+            Return::Unit => quote! { #call; },
+            Return::Fallible => quote! { #call?; },
+        };
+
+        // Both the registered import and the shim funnel through this:
+        // it's the single place that charges the flat dispatch cost,
+        // marshals `(ptr, len)` arguments, and calls the trait method.
+        dispatch_fns.push(quote! {
+            fn #dispatch_fn(
+                env: &mut WasmEnv,
+                host: &dyn #trait_name,
+                #(#params),*
+            ) -> ::eyre::Result<()> {
+                env.buy_ink(host.ink_cost(#import_name))?;
+                #(#marshal)*
+                #call
+                Ok(())
+            }
+        });
+
+        let host_params = args.iter().map(|a| &a.param);
+        let forward_args = args.iter().map(|a| &a.forward);
+
+        registrations.push(quote! {
+            imports.define(
+                "env",
+                #import_name,
+                ::wasmer::Function::new_typed_with_env(
+                    store,
+                    env,
+                    move |mut ctx: ::wasmer::FunctionEnvMut<WasmEnv>, #(#host_params),*| -> ::eyre::Result<()> {
+                        let env = ctx.data_mut();
+                        #dispatch_fn(env, host, #(#forward_args),*)
+                    },
+                ),
+            );
+        });
+
+        let shim_params = args.iter().map(|a| &a.param);
+        let shim_forward = args.iter().map(|a| &a.forward);
+        shim_methods.push(quote! {
+            pub fn #name(&self, env: &mut WasmEnv, #(#shim_params),*) -> ::eyre::Result<()> {
+                #dispatch_fn(env, self.host, #(#shim_forward),*)
+            }
+        });
+    }
+
+    let expanded = quote! {
+        #trait_item
+
+        #(#dispatch_fns)*
+
+        /// Registers every method of `#trait_name` as a wasmer import
+        /// bound to `env`, charging ink for each call before it runs.
+        pub fn #imports_fn(
+            store: &mut ::wasmer::Store,
+            env: &::wasmer::FunctionEnv<WasmEnv>,
+            host: &'static dyn #trait_name,
+        ) -> ::wasmer::Imports {
+            let mut imports = ::wasmer::Imports::new();
+            #(#registrations)*
+            imports
+        }
+
+        /// Typed client shim so tests can invoke the host functions above
+        /// directly, without wiring a guest `Instance` (a host import
+        /// can't be looked up through `Instance::exports` anyway — only
+        /// a running guest can call it).
+        pub struct #shim_name {
+            pub host: &'static dyn #trait_name,
+        }
+
+        impl #shim_name {
+            #(#shim_methods)*
+        }
+    };
+
+    expanded.into()
+}
+
+fn to_snake(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, ch) in name.char_indices() {
+        if ch.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
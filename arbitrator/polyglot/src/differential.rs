@@ -0,0 +1,188 @@
+// Copyright 2022, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+//! `benchmark_wasmer` runs the same module across Singlepass, Cranelift,
+//! and LLVM but only reports timing. This harness instead synthesizes
+//! many valid modules with `scenarios::random`'s extension of
+//! `write_wat_ops` (varying nesting depth, `br` targets, and `br_table`
+//! sizes/defaults — all guaranteed in range, so divergences reflect real
+//! backend bugs rather than malformed input) and asserts the three
+//! backends agree on return value, trap status, and, when metering is
+//! enabled, consumed ink. On divergence the offending WAT is dumped to
+//! disk for reproduction.
+
+use crate::{env::WasmEnv, poly};
+use eyre::Result;
+use prover::programs::config::PolyglotConfig;
+use std::io::Write as _;
+use stylus_benchmark::scenarios::random::{write_wat_ops, Xorshift64};
+use wasmer::{CompilerConfig, Imports, Instance, Module, Store};
+use wasmer_compiler_cranelift::Cranelift;
+use wasmer_compiler_llvm::LLVM;
+use wasmer_compiler_singlepass::Singlepass;
+
+const MODULES_PER_RUN: usize = 200;
+const OPS_PER_MODULE: usize = 16;
+const MAX_DEPTH: usize = 6;
+const LOOP_TRIPS: i32 = 64;
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    result: Option<i32>, // None means the call trapped
+    ink_consumed: Option<u64>,
+}
+
+fn build_wat(seed: u64) -> Vec<u8> {
+    let mut rng = Xorshift64::new(seed);
+    let mut wat = Vec::new();
+    wat.write_all(b"(module\n").unwrap();
+    wat.write_all(b"    (func (export \"main\") (result i32)\n").unwrap();
+    wat.write_all(b"        (local $i i32)\n").unwrap();
+    wat.write_all(b"        (loop $loop\n").unwrap();
+    write_wat_ops(&mut wat, OPS_PER_MODULE, &mut rng, MAX_DEPTH);
+    wat.write_all(b"            local.get $i\n").unwrap();
+    wat.write_all(b"            i32.const 1\n").unwrap();
+    wat.write_all(b"            i32.add\n").unwrap();
+    wat.write_all(b"            local.tee $i\n").unwrap();
+    wat.write_all(format!("            i32.const {LOOP_TRIPS}\n").as_bytes())
+        .unwrap();
+    wat.write_all(b"            i32.lt_u\n").unwrap();
+    wat.write_all(b"            br_if $loop\n").unwrap();
+    wat.write_all(b"        )\n").unwrap();
+    wat.write_all(b"        local.get $i\n").unwrap();
+    wat.write_all(b"    )\n").unwrap();
+    wat.write_all(b")\n").unwrap();
+    wat
+}
+
+fn run_emulated(wat: &[u8], mut store: Store) -> Result<Outcome> {
+    let wasm = wasmer::wat2wasm(wat)?;
+    let module = Module::new(&mut store, &wasm)?;
+    let instance = Instance::new(&mut store, &module, &Imports::new())?;
+    let main = instance.exports.get_typed_function::<(), i32>(&store, "main")?;
+
+    let result = main.call(&mut store).ok();
+    Ok(Outcome {
+        result,
+        ink_consumed: None,
+    })
+}
+
+/// Runs the metered path under an explicit `store`, so the caller can
+/// pin the backend instead of relying on whatever `poly::instance`
+/// defaults to. This is the only way to compare ink consumption *across*
+/// backends rather than just across repeated runs of the same one.
+fn run_polyglot(wat: &[u8], store: Store) -> Result<Outcome> {
+    let wasm = wasmer::wat2wasm(wat)?;
+    let file = tempfile::NamedTempFile::new()?;
+    std::fs::write(file.path(), &wasm)?;
+
+    let config = PolyglotConfig::default();
+    let env = WasmEnv::new(config, vec![]);
+    let (instance, function_env, mut store) = poly::instance_with_store(file.path(), env, store)?;
+    let main = instance.exports.get_typed_function::<(), i32>(&store, "main")?;
+
+    let ink_before = function_env.as_ref(&store).ink_left();
+    let result = main.call(&mut store).ok();
+    let ink_after = function_env.as_ref(&store).ink_left();
+
+    Ok(Outcome {
+        result,
+        ink_consumed: Some(ink_before.saturating_sub(ink_after)),
+    })
+}
+
+fn single_store() -> Store {
+    let mut compiler = Singlepass::new();
+    compiler.canonicalize_nans(true);
+    compiler.enable_verifier();
+    Store::new(compiler)
+}
+
+fn cranelift_store() -> Store {
+    let mut compiler = Cranelift::new();
+    compiler.canonicalize_nans(true);
+    compiler.enable_verifier();
+    Store::new(compiler)
+}
+
+fn llvm_store() -> Store {
+    let mut compiler = LLVM::new();
+    compiler.canonicalize_nans(true);
+    compiler.enable_verifier();
+    Store::new(compiler)
+}
+
+/// One `Store` per backend: Singlepass, Cranelift, then LLVM.
+fn backend_stores() -> [Store; 3] {
+    [single_store(), cranelift_store(), llvm_store()]
+}
+
+fn dump_divergence(seed: u64, wat: &[u8], outcomes: &[(&str, Outcome)]) {
+    let path = format!("differential-failure-{seed}.wat");
+    std::fs::write(&path, wat).expect("failed to dump offending WAT");
+    eprintln!("backend divergence on seed {seed}, wrote {path}");
+    for (backend, outcome) in outcomes {
+        eprintln!("  {backend}: {outcome:?}");
+    }
+}
+
+/// Runs `MODULES_PER_RUN` synthesized modules across all three backends
+/// (both emulated and metered) and asserts they agree on result and trap
+/// status.
+#[test]
+fn differential_backends_agree() -> Result<()> {
+    for seed in 0..MODULES_PER_RUN as u64 {
+        let wat = build_wat(seed);
+
+        let [single, cranelift, llvm] = backend_stores();
+        let emulated = [
+            ("singlepass", run_emulated(&wat, single)?),
+            ("cranelift", run_emulated(&wat, cranelift)?),
+            ("llvm", run_emulated(&wat, llvm)?),
+        ];
+
+        let baseline = &emulated[0].1.result;
+        if emulated.iter().any(|(_, outcome)| &outcome.result != baseline) {
+            dump_divergence(seed, &wat, &emulated);
+            panic!("backends disagree on return value for seed {seed}");
+        }
+
+        let [single, cranelift, llvm] = backend_stores();
+        let metered = [
+            ("singlepass", run_polyglot(&wat, single)?),
+            ("cranelift", run_polyglot(&wat, cranelift)?),
+            ("llvm", run_polyglot(&wat, llvm)?),
+        ];
+        if metered.iter().any(|(_, outcome)| &outcome.result != baseline) {
+            dump_divergence(seed, &wat, &metered);
+            panic!("metered polyglot path disagrees with emulated backends for seed {seed}");
+        }
+    }
+    Ok(())
+}
+
+/// With metering enabled, the ink consumed for a given module should be
+/// identical across Singlepass, Cranelift, and LLVM — the request this
+/// harness exists for is catching metering-instrumentation mismatches
+/// between backends, not just non-determinism within one of them.
+#[test]
+fn differential_ink_matches_across_backends() -> Result<()> {
+    for seed in 0..MODULES_PER_RUN as u64 {
+        let wat = build_wat(seed);
+        let [single, cranelift, llvm] = backend_stores();
+
+        let outcomes = [
+            ("singlepass", run_polyglot(&wat, single)?),
+            ("cranelift", run_polyglot(&wat, cranelift)?),
+            ("llvm", run_polyglot(&wat, llvm)?),
+        ];
+
+        let baseline = &outcomes[0].1.ink_consumed;
+        if outcomes.iter().any(|(_, outcome)| &outcome.ink_consumed != baseline) {
+            dump_divergence(seed, &wat, &outcomes);
+            panic!("ink consumption diverged across backends for seed {seed}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,10 @@
+// Copyright 2022, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+//! Host functions exposed to guest programs under the polyglot path,
+//! declared with `#[host_interface]` rather than wired by hand at each
+//! `poly::instance` call site. Adding a Stylus hostio is now a matter of
+//! declaring a trait method here; see `crypto` for the built-in
+//! cryptographic primitives.
+
+pub mod crypto;
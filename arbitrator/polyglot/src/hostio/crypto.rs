@@ -0,0 +1,152 @@
+// Copyright 2022, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+//! Cryptographic hostios backed by `arbutil::crypto`, so guest programs
+//! call into the host's implementation instead of bundling their own
+//! (the `tests/keccak` module ships a guest-side keccak for exactly this
+//! reason). Each hostio reads its input from guest memory at `(ptr,
+//! len)`, computes the digest on the host, and writes the result back to
+//! a guest-supplied output pointer, charging ink proportional to the
+//! input length on top of the flat per-call dispatch cost. Insufficient
+//! ink or an out-of-bounds guest pointer are both attacker-reachable, so
+//! each method returns `Result<()>` and traps the call instead of
+//! panicking the host.
+
+use crate::env::WasmEnv;
+use arbutil::crypto;
+use eyre::Result;
+use polyglot_macros::host_interface;
+
+/// Per-call dispatch cost charged before marshaling; length-proportional
+/// cost is charged separately inside each method via `env.buy_ink`.
+pub trait InkCost {
+    fn ink_cost(&self, hostio: &str) -> u64;
+}
+
+/// Ink charged per byte of input, beyond the flat dispatch cost.
+const INK_PER_BYTE: u64 = 10;
+
+#[host_interface]
+pub trait Crypto: InkCost {
+    fn keccak256(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()>;
+    fn sha256(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()>;
+    fn ecrecover(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()>;
+    fn blake2(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()>;
+}
+
+/// The host-side implementation of [`Crypto`], backed by `arbutil::crypto`.
+pub struct HostCrypto;
+
+impl InkCost for HostCrypto {
+    fn ink_cost(&self, _hostio: &str) -> u64 {
+        // flat dispatch cost; the bulk of the charge is per-byte, below.
+        50
+    }
+}
+
+impl Crypto for HostCrypto {
+    fn keccak256(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()> {
+        env.buy_ink(INK_PER_BYTE * input_len as u64)?;
+        let input = env.read_slice(input_ptr, input_len)?;
+        let digest = crypto::keccak(&input);
+        env.write_slice(output_ptr, &digest)?;
+        Ok(())
+    }
+
+    fn sha256(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()> {
+        env.buy_ink(INK_PER_BYTE * input_len as u64)?;
+        let input = env.read_slice(input_ptr, input_len)?;
+        let digest = crypto::sha256(&input);
+        env.write_slice(output_ptr, &digest)?;
+        Ok(())
+    }
+
+    fn ecrecover(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()> {
+        env.buy_ink(INK_PER_BYTE * input_len as u64)?;
+        let input = env.read_slice(input_ptr, input_len)?;
+        let address = crypto::ecrecover(&input)?;
+        env.write_slice(output_ptr, &address)?;
+        Ok(())
+    }
+
+    fn blake2(&self, env: &mut WasmEnv, input_ptr: u32, input_len: u32, output_ptr: u32) -> Result<()> {
+        env.buy_ink(INK_PER_BYTE * input_len as u64)?;
+        let input = env.read_slice(input_ptr, input_len)?;
+        let digest = crypto::blake2(&input);
+        env.write_slice(output_ptr, &digest)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poly;
+    use prover::programs::config::PolyglotConfig;
+
+    static HOST: HostCrypto = HostCrypto;
+
+    /// The guest calls the `keccak256` hostio instead of computing the
+    /// digest itself; its result should match the `native()` loop in
+    /// `benchmarks.rs`.
+    #[test]
+    fn keccak_hostio_matches_native() -> eyre::Result<()> {
+        let config = PolyglotConfig::default();
+        let env = WasmEnv::new(config, vec![]);
+
+        let file = "tests/keccak-hostio/target/wasm32-unknown-unknown/release/keccak_hostio.wasm";
+        let (instance, _, mut store) = poly::instance_with_imports(file, env, |store, env| {
+            crypto_imports(store, env, &HOST)
+        })?;
+
+        let main = instance
+            .exports
+            .get_typed_function::<i32, i32>(&store, "arbitrum_main")?;
+        let status = main.call(&mut store, 1)?;
+        assert_eq!(status, 0);
+        Ok(())
+    }
+
+    /// `CryptoShim` lets a test call a hostio directly, where
+    /// `keccak_hostio_matches_native` has to run a whole guest program to
+    /// exercise the same code path. The shim still needs a `WasmEnv` with
+    /// real guest memory behind it (`read_slice`/`write_slice` aren't
+    /// mockable), so it borrows one from an instantiated module rather
+    /// than constructing memory by hand.
+    #[test]
+    fn keccak_shim_matches_native() -> eyre::Result<()> {
+        let config = PolyglotConfig::default();
+        let env = WasmEnv::new(config, vec![]);
+
+        let file = "tests/keccak-hostio/target/wasm32-unknown-unknown/release/keccak_hostio.wasm";
+        let (_, function_env, mut store) =
+            poly::instance_with_imports(file, env, |store, env| crypto_imports(store, env, &HOST))?;
+
+        let shim = CryptoShim { host: &HOST };
+        let input = b"shim test input";
+        let input_ptr = 0;
+        let output_ptr = input.len() as u32;
+
+        let wasm_env = function_env.as_mut(&mut store);
+        wasm_env.write_slice(input_ptr, input)?;
+        shim.keccak256(wasm_env, input_ptr, input.len() as u32, output_ptr)?;
+        let digest = wasm_env.read_slice(output_ptr, 32)?;
+        assert_eq!(digest, crypto::keccak(input));
+        Ok(())
+    }
+
+    /// The host-backed module should be much smaller than the
+    /// self-contained `tests/keccak`, which bundles its own keccak.
+    #[test]
+    fn keccak_hostio_is_smaller_than_self_contained() -> eyre::Result<()> {
+        let hostio = std::fs::metadata(
+            "tests/keccak-hostio/target/wasm32-unknown-unknown/release/keccak_hostio.wasm",
+        )?
+        .len();
+        let self_contained =
+            std::fs::metadata("tests/keccak/target/wasm32-unknown-unknown/release/keccak.wasm")?
+                .len();
+        assert!(hostio < self_contained);
+        Ok(())
+    }
+}
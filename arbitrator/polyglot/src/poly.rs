@@ -0,0 +1,64 @@
+// Copyright 2022, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+//! Instantiates a compiled Stylus program under the polyglot (metered)
+//! path. `instance` covers the common case — a default store, no
+//! host-provided imports beyond what the module itself needs — while
+//! `instance_with_store` and `instance_with_imports` let a caller pin the
+//! backend or register hostios, for callers like the differential
+//! harness and the crypto hostio tests that need control `instance`
+//! doesn't expose.
+
+use crate::env::WasmEnv;
+use eyre::Result;
+use std::path::Path;
+use wasmer::{FunctionEnv, Imports, Instance, Module, Store};
+use wasmer_compiler_cranelift::Cranelift;
+
+fn default_store() -> Store {
+    Store::new(Cranelift::new())
+}
+
+/// Instantiates `file` against `store`, registering whatever `imports_fn`
+/// builds (it's handed the `FunctionEnv` so it can bind host functions to
+/// the same `env` the guest will run against).
+fn instantiate(
+    file: impl AsRef<Path>,
+    env: WasmEnv,
+    mut store: Store,
+    imports_fn: impl FnOnce(&mut Store, &FunctionEnv<WasmEnv>) -> Imports,
+) -> Result<(Instance, FunctionEnv<WasmEnv>, Store)> {
+    let wasm = std::fs::read(file)?;
+    let module = Module::new(&mut store, &wasm)?;
+    let function_env = FunctionEnv::new(&mut store, env);
+    let imports = imports_fn(&mut store, &function_env);
+    let instance = Instance::new(&mut store, &module, &imports)?;
+    Ok((instance, function_env, store))
+}
+
+/// Instantiates `file` with a default (Cranelift) store and no
+/// host-provided imports.
+pub fn instance(file: impl AsRef<Path>, env: WasmEnv) -> Result<(Instance, FunctionEnv<WasmEnv>, Store)> {
+    instantiate(file, env, default_store(), |_, _| Imports::new())
+}
+
+/// Instantiates `file` under an explicit `store`, so a caller can pin the
+/// compiler backend instead of taking the default.
+pub fn instance_with_store(
+    file: impl AsRef<Path>,
+    env: WasmEnv,
+    store: Store,
+) -> Result<(Instance, FunctionEnv<WasmEnv>, Store)> {
+    instantiate(file, env, store, |_, _| Imports::new())
+}
+
+/// Instantiates `file` with a default store, registering host-provided
+/// imports built by `imports_fn` (typically a `#[host_interface]`-generated
+/// `*_imports` function).
+pub fn instance_with_imports(
+    file: impl AsRef<Path>,
+    env: WasmEnv,
+    imports_fn: impl FnOnce(&mut Store, &FunctionEnv<WasmEnv>) -> Imports,
+) -> Result<(Instance, FunctionEnv<WasmEnv>, Store)> {
+    instantiate(file, env, default_store(), imports_fn)
+}
@@ -0,0 +1,258 @@
+// Copyright 2021-2026, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! Derives per-opcode ink costs by timing the `scenarios` WAT generators
+//! across a sweep of op counts and fitting a line to the measurements.
+//!
+//! Each point is run `SAMPLES_PER_POINT` times and the minimum is kept,
+//! to keep scheduler noise out of the numbers. The loop trip count is high enough that the per-op slope
+//! dominates dispatch overhead, and checking the loop counter against
+//! `LOOP_TRIPS` (the same idea as `native()`'s `assert_ne!`, but with a
+//! value the computation could actually produce) keeps the optimizer
+//! from eliding the loop entirely.
+
+use crate::scenarios::{br, br_table};
+use eyre::{bail, Result};
+use polyglot::{env::WasmEnv, poly};
+use prover::programs::config::PolyglotConfig;
+use std::io::Write as _;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+use wasmer::{wat2wasm, CompilerConfig, Imports, Instance, Module, Store};
+use wasmer_compiler_cranelift::{Cranelift, CraneliftOptLevel};
+use wasmer_compiler_llvm::{LLVMOptLevel, LLVM};
+use wasmer_compiler_singlepass::Singlepass;
+
+/// Per-point op counts. `0` anchors the intercept (loop/dispatch overhead
+/// with no opcode of interest at all).
+const OP_COUNTS: [usize; 5] = [0, 32, 64, 128, 256];
+
+/// Trip count of the wrapping loop; high enough that jitter in a single
+/// iteration is swamped by the aggregate.
+const LOOP_TRIPS: i32 = 20_000;
+
+/// Repeats per (class, op count) pair; we keep the minimum observed.
+const SAMPLES_PER_POINT: usize = 7;
+
+/// An opcode family the harness knows how to synthesize via `scenarios`.
+#[derive(Clone, Copy, Debug)]
+pub enum OpcodeClass {
+    Br,
+    BrTable { table_size: usize },
+}
+
+impl OpcodeClass {
+    fn label(&self) -> String {
+        match self {
+            OpcodeClass::Br => "br".to_string(),
+            OpcodeClass::BrTable { table_size } => format!("br_table[{table_size}]"),
+        }
+    }
+
+    fn write_ops(&self, wat: &mut Vec<u8>, ops_per_iteration: usize) {
+        match self {
+            OpcodeClass::Br => br::write_wat_ops(wat, ops_per_iteration),
+            OpcodeClass::BrTable { table_size } => {
+                br_table::write_wat_ops(wat, ops_per_iteration, *table_size)
+            }
+        }
+    }
+}
+
+/// Backends to calibrate against, mirroring `benchmark_wasmer`'s trio.
+#[derive(Clone, Copy, Debug)]
+pub enum Backend {
+    Singlepass,
+    Cranelift,
+    Llvm,
+}
+
+impl Backend {
+    fn store(&self) -> Store {
+        match self {
+            Backend::Singlepass => {
+                let mut compiler = Singlepass::new();
+                compiler.canonicalize_nans(true);
+                compiler.enable_verifier();
+                Store::new(compiler)
+            }
+            Backend::Cranelift => {
+                let mut compiler = Cranelift::new();
+                compiler.canonicalize_nans(true);
+                compiler.enable_verifier();
+                compiler.opt_level(CraneliftOptLevel::Speed);
+                Store::new(compiler)
+            }
+            Backend::Llvm => {
+                let mut compiler = LLVM::new();
+                compiler.canonicalize_nans(true);
+                compiler.enable_verifier();
+                compiler.opt_level(LLVMOptLevel::Aggressive);
+                Store::new(compiler)
+            }
+        }
+    }
+}
+
+/// The recovered marginal cost of one opcode, in nanoseconds, plus the
+/// fixed overhead the regression attributed to the loop and dispatch.
+#[derive(Clone, Debug)]
+pub struct Calibration {
+    pub class: String,
+    pub backend: &'static str,
+    pub per_op_ns: f64,
+    pub intercept_ns: f64,
+}
+
+/// Wraps a family's ops in a loop so a handful of op counts still yield
+/// a measurable, low-noise wall-time for `main(_, _) -> i32`, which
+/// ignores its params and returns the `LOOP_TRIPS` trip count.
+fn build_wat(class: OpcodeClass, ops_per_iteration: usize) -> Vec<u8> {
+    let mut wat = Vec::new();
+    wat.write_all(b"(module\n").unwrap();
+    wat.write_all(b"    (func (export \"main\") (param i32 i32) (result i32)\n").unwrap();
+    wat.write_all(b"        (local $i i32)\n").unwrap();
+    wat.write_all(b"        (loop $loop\n").unwrap();
+    class.write_ops(&mut wat, ops_per_iteration);
+    wat.write_all(b"            local.get $i\n").unwrap();
+    wat.write_all(b"            i32.const 1\n").unwrap();
+    wat.write_all(b"            i32.add\n").unwrap();
+    wat.write_all(b"            local.tee $i\n").unwrap();
+    wat.write_all(format!("            i32.const {LOOP_TRIPS}\n").as_bytes())
+        .unwrap();
+    wat.write_all(b"            i32.lt_u\n").unwrap();
+    wat.write_all(b"            br_if $loop\n").unwrap();
+    wat.write_all(b"        )\n").unwrap();
+    wat.write_all(b"        local.get $i\n").unwrap();
+    wat.write_all(b"    )\n").unwrap();
+    wat.write_all(b")\n").unwrap();
+    wat
+}
+
+/// Times the emulated (plain wasmer) path for a synthesized module.
+fn time_emulated(backend: Backend, wasm: &[u8]) -> Result<Duration> {
+    let mut store = backend.store();
+    let module = Module::new(&mut store, wasm)?;
+    let instance = Instance::new(&mut store, &module, &Imports::new())?;
+    let main = instance
+        .exports
+        .get_typed_function::<(i32, i32), i32>(&store, "main")?;
+
+    let time = Instant::now();
+    let result = main.call(&mut store, 0, 0)?;
+    if result != LOOP_TRIPS {
+        bail!("loop ran {result} times, expected {LOOP_TRIPS}; optimizer may have elided it");
+    }
+    Ok(time.elapsed())
+}
+
+/// Times the polyglot (metered) path for a synthesized module. `poly::instance`
+/// only takes a file path, so the generated wasm is spilled to a tempfile
+/// first, the same way the `emulated`/`polyglot` benchmarks read their
+/// modules from `tests/keccak*`.
+fn time_polyglot(wasm: &[u8]) -> Result<Duration> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(wasm)?;
+
+    let config = PolyglotConfig::default();
+    let env = WasmEnv::new(config, vec![]);
+    let (instance, _, mut store) = poly::instance(file.path(), env)?;
+    let main = instance
+        .exports
+        .get_typed_function::<(i32, i32), i32>(&store, "main")?;
+
+    let time = Instant::now();
+    let result = main.call(&mut store, 0, 0)?;
+    if result != LOOP_TRIPS {
+        bail!("loop ran {result} times, expected {LOOP_TRIPS}; optimizer may have elided it");
+    }
+    Ok(time.elapsed())
+}
+
+/// Measures the minimum wall-time of `SAMPLES_PER_POINT` runs.
+fn measure(mut run: impl FnMut() -> Result<Duration>) -> Result<Duration> {
+    let mut best = None;
+    for _ in 0..SAMPLES_PER_POINT {
+        let elapsed = run()?;
+        best = Some(best.map_or(elapsed, |b: Duration| b.min(elapsed)));
+    }
+    best.ok_or_else(|| eyre::eyre!("no samples collected"))
+}
+
+/// Least-squares slope and intercept of `y` against `x`; the slope is the
+/// marginal per-op cost, and the intercept absorbs loop/dispatch overhead.
+fn least_squares(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let sum_x: f64 = points.iter().map(|(x, _)| x).sum();
+    let sum_y: f64 = points.iter().map(|(_, y)| y).sum();
+    let sum_xx: f64 = points.iter().map(|(x, _)| x * x).sum();
+    let sum_xy: f64 = points.iter().map(|(x, y)| x * y).sum();
+
+    let denom = n * sum_xx - sum_x * sum_x;
+    let slope = (n * sum_xy - sum_x * sum_y) / denom;
+    let intercept = (sum_y - slope * sum_x) / n;
+    (slope, intercept)
+}
+
+/// Calibrates a single opcode class on a single backend, running the
+/// `emulated` path (the polyglot path is metering-instrumented and would
+/// bias the regression toward the ink charge rather than raw op cost).
+pub fn calibrate(class: OpcodeClass, backend: Backend) -> Result<Calibration> {
+    let mut points = Vec::with_capacity(OP_COUNTS.len());
+    for &k in &OP_COUNTS {
+        let wat = build_wat(class, k);
+        let wasm = wat2wasm(&wat)?;
+        let elapsed = measure(|| time_emulated(backend, &wasm))?;
+        // each of the `k` ops runs once per trip, so regress against the
+        // total number of ops executed to get a per-op slope
+        points.push((k as f64 * LOOP_TRIPS as f64, elapsed.as_nanos() as f64));
+    }
+
+    let (per_op_ns, intercept_ns) = least_squares(&points);
+    Ok(Calibration {
+        class: class.label(),
+        backend: match backend {
+            Backend::Singlepass => "singlepass",
+            Backend::Cranelift => "cranelift",
+            Backend::Llvm => "llvm",
+        },
+        per_op_ns,
+        intercept_ns,
+    })
+}
+
+/// Times the polyglot (metered) path at the largest op count, for
+/// printing beside the `emulated` rows. It measures wall-time only: no
+/// ink is read, and nothing is compared against the regression.
+pub fn sanity_check_polyglot(class: OpcodeClass) -> Result<Duration> {
+    let wat = build_wat(class, OP_COUNTS[OP_COUNTS.len() - 1]);
+    let wasm = wat2wasm(&wat)?;
+    measure(|| time_polyglot(&wasm))
+}
+
+/// Runs the full sweep (every class, every backend) and prints a cost
+/// table, one row per (opcode-class, backend) pair.
+#[test]
+fn calibrate_ink_costs() -> Result<()> {
+    let classes = [
+        OpcodeClass::Br,
+        OpcodeClass::BrTable { table_size: 4 },
+        OpcodeClass::BrTable { table_size: 16 },
+    ];
+    let backends = [Backend::Singlepass, Backend::Cranelift, Backend::Llvm];
+
+    println!("{:<16} {:<12} {:>14} {:>14}", "class", "backend", "ns/op", "intercept_ns");
+    for class in classes {
+        for backend in backends {
+            let calibration = calibrate(class, backend)?;
+            println!(
+                "{:<16} {:<12} {:>14.2} {:>14.2}",
+                calibration.class, calibration.backend, calibration.per_op_ns, calibration.intercept_ns
+            );
+        }
+
+        let polyglot = sanity_check_polyglot(class)?;
+        println!("{:<16} {:<12} {:>14} {:>14.2?}", class.label(), "polyglot", "-", polyglot);
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+// Copyright 2021-2026, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+pub mod br;
+pub mod br_table;
+pub mod random;
@@ -0,0 +1,102 @@
+// Copyright 2021-2026, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! A randomized extension of `br`/`br_table`'s `write_wat_ops`: instead of
+//! a fixed nesting depth and table shape, each call picks its own depth,
+//! `br` target, and `br_table` size/default, while still guaranteeing
+//! every emitted index is in range. This is for the differential harness
+//! in `polyglot`'s `differential.rs`, which wants many *valid* modules so
+//! any divergence between backends reflects a real compiler bug rather
+//! than malformed input.
+
+use std::io::Write;
+
+/// A source of randomness abstracted behind a trait so the differential
+/// harness can seed it deterministically and reproduce a failing case.
+pub trait Rng {
+    /// Returns a value in `0..bound` (`bound` must be nonzero).
+    fn gen_range(&mut self, bound: usize) -> usize;
+}
+
+/// A small xorshift generator; good enough for fuzzing WAT shapes and
+/// trivially reproducible from a single `u64` seed.
+pub struct Xorshift64(u64);
+
+impl Xorshift64 {
+    pub fn new(seed: u64) -> Self {
+        Xorshift64(seed | 1)
+    }
+}
+
+impl Rng for Xorshift64 {
+    fn gen_range(&mut self, bound: usize) -> usize {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (x as usize) % bound
+    }
+}
+
+/// Emits one randomly-nested `block`/`br` construct, at a depth in
+/// `1..=max_depth`, always targeting a valid enclosing block.
+fn write_random_br(wat: &mut Vec<u8>, rng: &mut impl Rng, indent: &str, max_depth: usize) {
+    let depth = 1 + rng.gen_range(max_depth);
+    let target = rng.gen_range(depth); // always < depth, so always in range
+
+    let mut nested = indent.to_string();
+    for _ in 0..depth {
+        wat.write_all(format!("{nested}(block\n").as_bytes()).unwrap();
+        nested.push_str("    ");
+    }
+    wat.write_all(format!("{nested}br {target}\n").as_bytes()).unwrap();
+    for _ in 0..depth {
+        nested.truncate(nested.len() - 4);
+        wat.write_all(format!("{nested})\n").as_bytes()).unwrap();
+    }
+}
+
+/// Emits one randomly-sized `br_table`, at a depth (and so table size) in
+/// `1..=max_table_size`, with a random (always in-range) default target.
+fn write_random_br_table(wat: &mut Vec<u8>, rng: &mut impl Rng, indent: &str, max_table_size: usize) {
+    let table_size = 1 + rng.gen_range(max_table_size);
+    let selector = rng.gen_range(table_size * 2) as i32; // may exceed every case, exercising the default
+    let default = rng.gen_range(table_size); // always a valid block index
+
+    let mut nested = indent.to_string();
+    for _ in 0..table_size {
+        wat.write_all(format!("{nested}(block\n").as_bytes()).unwrap();
+        nested.push_str("    ");
+    }
+    wat.write_all(format!("{nested}i32.const {selector}\n").as_bytes()).unwrap();
+
+    let mut br_table = String::from("br_table");
+    for i in 0..table_size {
+        br_table.push_str(&format!(" {i}"));
+    }
+    br_table.push_str(&format!(" {default}")); // trailing entry is the default
+    wat.write_all(format!("{nested}{br_table}\n").as_bytes()).unwrap();
+
+    for _ in 0..table_size {
+        nested.truncate(nested.len() - 4);
+        wat.write_all(format!("{nested})\n").as_bytes()).unwrap();
+    }
+}
+
+/// Synthesizes `number_of_ops_per_loop_iteration` randomly-shaped `br`
+/// and `br_table` constructs, mixed fifty-fifty.
+pub fn write_wat_ops(
+    wat: &mut Vec<u8>,
+    number_of_ops_per_loop_iteration: usize,
+    rng: &mut impl Rng,
+    max_depth: usize,
+) {
+    for _ in 0..number_of_ops_per_loop_iteration {
+        if rng.gen_range(2) == 0 {
+            write_random_br(wat, rng, "            ", max_depth);
+        } else {
+            write_random_br_table(wat, rng, "            ", max_depth);
+        }
+    }
+}